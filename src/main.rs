@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::env;
 
 use codegen::{Scope, Type};
@@ -10,52 +11,439 @@ async fn main() {
     do_it().await.unwrap();
 }
 
+/// Maps a Postgres `udt_name` for a user-defined enum or composite type to
+/// the Rust type we generated for it.
+type UserTypes = HashMap<String, String>;
+
+/// One `information_schema.columns` row: `(name, data_type, udt_name,
+/// is_nullable, has_server_value)`, where `has_server_value` is true for
+/// columns Postgres will fill in on its own (a `column_default` or an
+/// identity/generated column), so inserts/updates can leave them out.
+type Column = (String, String, String, String, bool);
+
+/// Controls the shape of the generated functions. Read once from the
+/// environment (and, for derives and the vector distance operator, optionally
+/// `rsdbgen.toml`) at the start of a run.
+struct GenConfig {
+    /// Generate functions generic over `E: sqlx::Executor<'c, Database = sqlx::Postgres>`
+    /// instead of hardwiring `&PgPool`, so callers can pass a pool, a
+    /// connection, or a transaction.
+    generic_executor: bool,
+    /// Generate blocking functions (wrapped in `block_on`) instead of `async fn`.
+    sync: bool,
+    /// Extra derives applied to both `Row` and `InputRow` structs, e.g.
+    /// `"FromRow"`, `"Serialize"`.
+    extra_derives: Vec<String>,
+    /// The pgvector distance operator used by generated similarity-search
+    /// functions: `<=>` (cosine), `<->` (L2), or `<#>` (inner product), to
+    /// match whichever index a table's vector column was built with.
+    vector_distance_op: String,
+    /// Print discovered foreign-key relationships to stderr while generating.
+    verbose: bool,
+}
+
+impl GenConfig {
+    fn from_env() -> Self {
+        let file = read_rsdbgen_toml().unwrap_or_default();
+
+        let mut extra_derives = file.derive;
+        if env_flag("RSDBGEN_DERIVE_FROM_ROW") {
+            push_unique(&mut extra_derives, "FromRow");
+        }
+        if env_flag("RSDBGEN_DERIVE_SERDE") {
+            push_unique(&mut extra_derives, "Serialize");
+            push_unique(&mut extra_derives, "Deserialize");
+        }
+        if env_flag("RSDBGEN_DERIVE_EQ") {
+            push_unique(&mut extra_derives, "PartialEq");
+            push_unique(&mut extra_derives, "Eq");
+        }
+        if env_flag("RSDBGEN_DERIVE_HASH") {
+            push_unique(&mut extra_derives, "Hash");
+        }
+
+        let vector_distance_op = env::var("RSDBGEN_VECTOR_DISTANCE_OP")
+            .ok()
+            .or(file.distance_op)
+            .unwrap_or_else(|| "<=>".to_string());
+        let vector_distance_op = match vector_distance_op.as_str() {
+            "<=>" | "<->" | "<#>" => vector_distance_op,
+            other => {
+                eprintln!(
+                    "warning: unknown vector distance operator `{}`, defaulting to cosine `<=>`",
+                    other
+                );
+                "<=>".to_string()
+            }
+        };
+
+        GenConfig {
+            generic_executor: env_flag("RSDBGEN_GENERIC_EXECUTOR"),
+            sync: env_flag("RSDBGEN_SYNC"),
+            extra_derives,
+            vector_distance_op,
+            verbose: env_flag("RSDBGEN_VERBOSE"),
+        }
+    }
+}
+
+fn env_flag(name: &str) -> bool {
+    matches!(env::var(name).as_deref(), Ok("1") | Ok("true"))
+}
+
+fn push_unique(derives: &mut Vec<String>, derive: &str) {
+    if !derives.iter().any(|d| d == derive) {
+        derives.push(derive.to_string());
+    }
+}
+
+/// An optional `rsdbgen.toml` in the working directory, e.g.:
+/// ```toml
+/// derive = ["FromRow", "Serialize", "Deserialize"]
+/// distance_op = "<->"
+/// ```
+#[derive(serde::Deserialize, Default)]
+struct RsdbgenToml {
+    #[serde(default)]
+    derive: Vec<String>,
+    #[serde(default)]
+    distance_op: Option<String>,
+}
+
+fn read_rsdbgen_toml() -> Option<RsdbgenToml> {
+    let contents = std::fs::read_to_string("rsdbgen.toml").ok()?;
+    match toml::from_str::<RsdbgenToml>(&contents) {
+        Ok(parsed) => Some(parsed),
+        Err(err) => {
+            eprintln!("warning: failed to parse rsdbgen.toml: {}", err);
+            None
+        }
+    }
+}
+
+/// Maps an extra derive name to the `scope.import` it needs to compile; derives
+/// like `PartialEq`/`Eq`/`Hash` are in the prelude and need none.
+fn derive_import(derive: &str) -> Option<(&'static str, &'static str)> {
+    match derive {
+        "FromRow" => Some(("sqlx", "FromRow")),
+        "Serialize" => Some(("serde", "Serialize")),
+        "Deserialize" => Some(("serde", "Deserialize")),
+        _ => None,
+    }
+}
+
 async fn do_it() -> Result<(), anyhow::Error> {
     let db_url = env::var("DATABASE_URL")?;
     let pool = PgPool::connect(&db_url).await?;
 
+    let mut scope = Scope::new();
+    scope.import("sqlx", "PgPool");
+
+    let config = GenConfig::from_env();
+    if config.sync {
+        add_block_on_helper(&mut scope);
+    }
+    for derive in &config.extra_derives {
+        if let Some((krate, item)) = derive_import(derive) {
+            scope.import(krate, item);
+        }
+    }
+
+    let user_types = add_user_defined_types(&mut scope, &pool).await?;
+    let primary_keys = fetch_primary_keys(&pool).await?;
+    let foreign_keys = fetch_foreign_keys(&pool).await?;
+    if config.verbose {
+        for (table_name, columns) in &foreign_keys {
+            for (column_name, foreign_table, foreign_column) in columns {
+                eprintln!(
+                    "note: {}.{} references {}.{}",
+                    table_name, column_name, foreign_table, foreign_column
+                );
+            }
+        }
+    }
+
     let tables = sqlx::query!(
-        "SELECT table_name, column_name, data_type, is_nullable FROM information_schema.columns WHERE table_schema='public' ORDER BY table_name, ordinal_position"
+        "SELECT table_name, column_name, data_type, udt_name, is_nullable, column_default, is_identity, is_generated \
+         FROM information_schema.columns WHERE table_schema='public' ORDER BY table_name, ordinal_position"
     )
     .fetch_all(&pool)
     .await?;
     let grouped = tables
         .into_iter()
         .group_by(|t| t.table_name.clone().unwrap());
-    let mut scope = Scope::new();
-    scope.import("sqlx", "PgPool");
     for (table_name, columns) in &grouped {
         //println!("{}", table.table_name.unwrap());
         if !should_emit(&table_name) {
             continue;
         }
 
-        let columns_vec: Vec<_> = columns
+        let columns_vec: Vec<Column> = columns
             .map(|c| {
+                let has_server_value = c.column_default.is_some()
+                    || c.is_identity.unwrap() == "YES"
+                    || c.is_generated.unwrap() == "ALWAYS";
                 (
                     c.column_name.unwrap(),
                     c.data_type.unwrap(),
+                    c.udt_name.unwrap(),
                     c.is_nullable.unwrap(),
+                    has_server_value,
                 )
             })
             .collect();
-        add_structs_for_table(&mut scope, &table_name, &columns_vec);
-        add_insert_for_table(&mut scope, &table_name, &columns_vec);
-        // add_select_for_table(&mut scope, &table_name, &columns_vec);
+        let pk_columns = primary_keys.get(&table_name).cloned().unwrap_or_default();
+        add_structs_for_table(&mut scope, &table_name, &columns_vec, &user_types, &pk_columns, &config);
+        add_insert_for_table(&mut scope, &table_name, &columns_vec, &pk_columns, &config);
+        add_bulk_insert_for_table(&mut scope, &table_name, &columns_vec, &pk_columns, &config);
+        add_select_for_table(&mut scope, &table_name, &columns_vec, &pk_columns, &user_types, &config);
+        add_update_for_table(&mut scope, &table_name, &columns_vec, &pk_columns, &user_types, &config);
+        add_delete_for_table(&mut scope, &table_name, &columns_vec, &pk_columns, &user_types, &config);
+        add_list_for_table(&mut scope, &table_name, &config);
+        let vector_columns: Vec<_> = columns_vec
+            .iter()
+            .filter(|(_, _, udt_name, _, _)| udt_name == "vector")
+            .collect();
+        if !vector_columns.is_empty() {
+            add_search_struct_for_table(&mut scope, &table_name, &columns_vec, &user_types, &config);
+            for (column_name, _, _, _, _) in vector_columns {
+                add_vector_search_for_table(&mut scope, &table_name, column_name, &columns_vec, &config);
+            }
+        }
     }
     println!("{}", scope.to_string());
     Ok(())
 }
 
+/// Discovers each table's primary-key columns, in constraint-ordinal order,
+/// so composite keys generate one `$n` parameter per column instead of
+/// assuming a single `id`.
+async fn fetch_primary_keys(pool: &PgPool) -> Result<HashMap<String, Vec<String>>, anyhow::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT tc.table_name, kcu.column_name
+        FROM information_schema.table_constraints tc
+        JOIN information_schema.key_column_usage kcu
+            ON tc.constraint_name = kcu.constraint_name
+            AND tc.table_schema = kcu.table_schema
+        WHERE tc.constraint_type = 'PRIMARY KEY' AND tc.table_schema = 'public'
+        ORDER BY tc.table_name, kcu.ordinal_position"#
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut primary_keys: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        primary_keys
+            .entry(row.table_name.unwrap())
+            .or_default()
+            .push(row.column_name.unwrap());
+    }
+    Ok(primary_keys)
+}
+
+/// Discovers each table's foreign-key columns and what they reference.
+async fn fetch_foreign_keys(
+    pool: &PgPool,
+) -> Result<HashMap<String, Vec<(String, String, String)>>, anyhow::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT tc.table_name, kcu.column_name, ccu.table_name AS foreign_table_name, ccu.column_name AS foreign_column_name
+        FROM information_schema.table_constraints tc
+        JOIN information_schema.key_column_usage kcu
+            ON tc.constraint_name = kcu.constraint_name AND tc.table_schema = kcu.table_schema
+        JOIN information_schema.constraint_column_usage ccu
+            ON tc.constraint_name = ccu.constraint_name AND tc.table_schema = ccu.table_schema
+        WHERE tc.constraint_type = 'FOREIGN KEY' AND tc.table_schema = 'public'
+        ORDER BY tc.table_name, kcu.ordinal_position"#
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut foreign_keys: HashMap<String, Vec<(String, String, String)>> = HashMap::new();
+    for row in rows {
+        foreign_keys.entry(row.table_name.unwrap()).or_default().push((
+            row.column_name.unwrap(),
+            row.foreign_table_name.unwrap(),
+            row.foreign_column_name.unwrap(),
+        ));
+    }
+    Ok(foreign_keys)
+}
+
+/// Queries the catalog for user-defined enum and composite types, emits a
+/// Rust `enum`/`struct` for each, and returns a `udt_name -> Rust type` map
+/// so columns typed `USER-DEFINED` can be resolved to them.
+async fn add_user_defined_types(
+    scope: &mut Scope,
+    pool: &PgPool,
+) -> Result<UserTypes, anyhow::Error> {
+    let mut user_types = UserTypes::new();
+
+    let enum_labels = sqlx::query!(
+        r#"SELECT t.typname, e.enumlabel
+        FROM pg_catalog.pg_type t
+        JOIN pg_catalog.pg_enum e ON e.enumtypid = t.oid
+        JOIN pg_catalog.pg_namespace n ON n.oid = t.typnamespace
+        WHERE n.nspname = 'public'
+        ORDER BY t.typname, e.enumsortorder"#
+    )
+    .fetch_all(pool)
+    .await?;
+    for (typname, labels) in &enum_labels
+        .into_iter()
+        .group_by(|r| r.typname.clone().unwrap())
+    {
+        let rust_name = to_class_case(&typname);
+        let variants = labels
+            .map(|l| {
+                let label = l.enumlabel.unwrap();
+                format!(
+                    "    #[sqlx(rename = \"{}\")]\n    {},",
+                    label,
+                    to_class_case(&label)
+                )
+            })
+            .join("\n");
+        scope.raw(&format!(
+            "#[derive(Debug, Clone, sqlx::Type)]\n#[sqlx(type_name = \"{}\")]\npub enum {} {{\n{}\n}}",
+            typname, rust_name, variants
+        ));
+        user_types.insert(typname, rust_name);
+    }
+
+    let composite_fields = sqlx::query!(
+        r#"SELECT c.relname AS typname, a.attname, t.typname::text AS atttype
+        FROM pg_catalog.pg_class c
+        JOIN pg_catalog.pg_attribute a ON a.attrelid = c.oid
+        JOIN pg_catalog.pg_type t ON t.oid = a.atttypid
+        JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.relkind = 'c' AND n.nspname = 'public' AND a.attnum > 0 AND NOT a.attisdropped
+        ORDER BY c.relname, a.attnum"#
+    )
+    .fetch_all(pool)
+    .await?;
+    let grouped = composite_fields
+        .into_iter()
+        .group_by(|r| r.typname.clone().unwrap());
+    let composites: Vec<(String, Vec<(String, String)>)> = (&grouped)
+        .into_iter()
+        .map(|(typname, fields)| {
+            let fields = fields
+                .map(|f| (f.attname.unwrap(), f.atttype.unwrap()))
+                .collect();
+            (typname, fields)
+        })
+        .collect();
+
+    // Register every composite type's Rust name before resolving any field
+    // types, so a field referencing another composite type that sorts later
+    // alphabetically still resolves instead of falling back to a placeholder.
+    for (typname, _) in &composites {
+        user_types.insert(typname.clone(), to_class_case(typname));
+    }
+
+    for (typname, fields) in &composites {
+        let rust_name = &user_types[typname];
+        let fields = fields
+            .iter()
+            .map(|(attname, atttype)| {
+                format!(
+                    "    pub {}: {},",
+                    attname,
+                    pg_typname_to_rs_type(atttype, &user_types)
+                )
+            })
+            .join("\n");
+        scope.raw(&format!(
+            "#[derive(Debug, Clone, sqlx::Type)]\n#[sqlx(type_name = \"{}\")]\npub struct {} {{\n{}\n}}",
+            typname, rust_name, fields
+        ));
+    }
+
+    Ok(user_types)
+}
+
 fn should_emit(table_name: &str) -> bool {
     table_name != "_sqlx_migrations"
 }
 
-fn add_insert_for_table(scope: &mut Scope, table_name: &str, columns: &[(String, String, String)]) {
+/// Emits the `block_on` helper the sync variant's functions call to drive
+/// their (still `.await`-based) bodies to completion. Lazily starts its own
+/// dedicated Tokio runtime rather than relying on `Handle::current()`, so
+/// these functions are callable from genuinely synchronous code with no
+/// ambient async runtime.
+fn add_block_on_helper(scope: &mut Scope) {
+    scope.raw(
+        "/// Blocks the current thread on `fut`, for the sync variant of the\n\
+/// generated functions below.\n\
+fn block_on<F: std::future::Future>(fut: F) -> F::Output {\n\
+    static RUNTIME: std::sync::OnceLock<tokio::runtime::Runtime> = std::sync::OnceLock::new();\n\
+    RUNTIME\n\
+        .get_or_init(|| tokio::runtime::Runtime::new().expect(\"failed to start Tokio runtime\"))\n\
+        .block_on(fut)\n\
+}",
+    );
+}
+
+/// True if any of `field_types` is a bare `f32`/`f64` (optionally wrapped in
+/// `Option<..>`), which implement neither `Eq` nor `Hash`.
+fn has_float_field<'a>(field_types: impl IntoIterator<Item = &'a str>) -> bool {
+    field_types
+        .into_iter()
+        .any(|ty| matches!(ty, "f32" | "f64" | "Option<f32>" | "Option<f64>"))
+}
+
+/// Applies `config.extra_derives` to `target`, skipping `Eq`/`Hash` (with a
+/// warning) when `has_float_field` is set, since deriving either on a struct
+/// with a float-typed field doesn't compile.
+fn add_extra_derives(target: &mut codegen::Struct, config: &GenConfig, struct_name: &str, has_float_field: bool) {
+    for derive in &config.extra_derives {
+        if has_float_field && (derive == "Eq" || derive == "Hash") {
+            eprintln!(
+                "warning: skipping derive({}) for {} because it has a float-typed column; f32/f64 implement neither",
+                derive, struct_name
+            );
+            continue;
+        }
+        target.derive(derive);
+    }
+}
+
+/// Adds the `conn` argument, generic over an executor when
+/// `config.generic_executor` is set, hardwired to `&PgPool` otherwise.
+fn add_conn_arg(new_fn: &mut codegen::Function, config: &GenConfig) {
+    if config.generic_executor {
+        new_fn.generic("'c");
+        new_fn.generic("E");
+        new_fn.bound("E", "sqlx::Executor<'c, Database = sqlx::Postgres>");
+        new_fn.arg("conn", Type::new("E"));
+    } else {
+        new_fn.arg("conn", Type::new("&PgPool"));
+    }
+}
+
+/// Finishes a function body: in async mode the `.await`-based body is used
+/// as-is, in sync mode it's wrapped in a call to `block_on`.
+fn finish_body(new_fn: &mut codegen::Function, config: &GenConfig, body: String) {
+    new_fn.set_async(!config.sync);
+    if config.sync {
+        let indented = body
+            .lines()
+            .map(|line| if line.is_empty() { line.to_string() } else { format!("    {}", line) })
+            .join("\n");
+        new_fn.line(format!("block_on(async move {{{}\n}})", indented));
+    } else {
+        new_fn.line(body);
+    }
+}
+
+fn add_insert_for_table(
+    scope: &mut Scope,
+    table_name: &str,
+    columns: &[Column],
+    pk_columns: &[String],
+    config: &GenConfig,
+) {
     let new_fn = scope.new_fn(&format!("insert_{}", table_name));
-    new_fn.set_async(true);
     new_fn.vis("pub");
-    new_fn.arg("conn", Type::new("&PgPool"));
+    add_conn_arg(new_fn, config);
     new_fn.arg(
         "row",
         Type::new(&format!("&{}", input_row_struct_name(table_name))),
@@ -66,7 +454,7 @@ fn add_insert_for_table(scope: &mut Scope, table_name: &str, columns: &[(String,
     )));
     let columns: Vec<_> = columns
         .iter()
-        .filter(|c| c.0 != "id" && c.0 != "created_at")
+        .filter(|c| !pk_columns.contains(&c.0) && !c.4)
         .collect();
     let insert_name_list = columns.iter().map(|c| format!("\"{}\"", c.0)).join(", ");
     let args_list = columns.iter().map(|c| format!("row.{}", c.0)).join(", ");
@@ -93,40 +481,334 @@ fn add_insert_for_table(scope: &mut Scope, table_name: &str, columns: &[(String,
         insert_placeholders,
         args_list
     );
-    new_fn.line(body);
+    finish_body(new_fn, config, body);
 }
 
-fn add_select_for_table(scope: &mut Scope, table_name: &str, columns: &[(String, String, String)]) {
-    let Some(id_typ) = &columns.iter().find(|c| c.0 == "id") else {
-        return
-    };
+/// The Postgres array-cast spelling for a column's element type, e.g.
+/// `int4[]` for a plain column or `int4[][]` for a column that is itself an
+/// array. `udt_name` already carries the underlying type name (prefixed with
+/// `_` for arrays), including user-defined enum/composite names, so no
+/// catalog lookup is needed here.
+fn pg_array_cast(data_type: &str, udt_name: &str) -> String {
+    if data_type == "ARRAY" {
+        format!("{}[][]", udt_name.trim_start_matches('_'))
+    } else {
+        format!("{}[]", udt_name)
+    }
+}
+
+/// Generates `insert_many_{table}`, a single-round-trip batch insert built on
+/// Postgres's `UNNEST`-based multi-row insert: each input column is pivoted
+/// into its own `Vec`, bound as one array parameter per column.
+fn add_bulk_insert_for_table(
+    scope: &mut Scope,
+    table_name: &str,
+    columns: &[Column],
+    pk_columns: &[String],
+    config: &GenConfig,
+) {
+    let new_fn = scope.new_fn(&format!("insert_many_{}", table_name));
+    new_fn.vis("pub");
+    add_conn_arg(new_fn, config);
+    new_fn.arg(
+        "rows",
+        Type::new(&format!("&[{}]", input_row_struct_name(table_name))),
+    );
+    new_fn.ret(Type::new(&format!(
+        "Result<Vec<{}>, sqlx::Error>",
+        row_struct_name(table_name)
+    )));
+
+    let insert_columns: Vec<_> = columns
+        .iter()
+        .filter(|c| !pk_columns.contains(&c.0) && !c.4)
+        .collect();
+    let insert_name_list = insert_columns
+        .iter()
+        .map(|c| format!("\"{}\"", c.0))
+        .join(", ");
+    let unnest_placeholders = insert_columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("${}::{}", i + 1, pg_array_cast(&c.1, &c.2)))
+        .join(", ");
+    let column_pivots = insert_columns
+        .iter()
+        .map(|c| {
+            format!(
+                "    let {}: Vec<_> = rows.iter().map(|row| row.{}.clone()).collect();",
+                c.0, c.0
+            )
+        })
+        .join("\n");
+    let args_list = insert_columns.iter().map(|c| format!("&{}", c.0)).join(", ");
+
+    let body = format!(
+        r##"
+{}
+    let result = sqlx::query_as!({},
+        r#"INSERT INTO {} ({})
+        SELECT * FROM UNNEST({})
+        RETURNING *"#,
+        {}
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(result)"##,
+        column_pivots,
+        row_struct_name(table_name),
+        table_name,
+        insert_name_list,
+        unnest_placeholders,
+        args_list
+    );
+    finish_body(new_fn, config, body);
+}
+
+/// Looks up the Rust type generated for a single column, by name.
+fn column_rs_type(
+    columns: &[Column],
+    column_name: &str,
+    user_types: &UserTypes,
+) -> String {
+    let column = columns
+        .iter()
+        .find(|c| c.0 == column_name)
+        .unwrap_or_else(|| panic!("key column `{}` not found among table columns", column_name));
+    let ty = pg_type_to_rs_type(&column.1, &column.2, user_types);
+    if column.3 == "YES" {
+        format!("Option<{}>", ty)
+    } else {
+        ty
+    }
+}
+
+fn add_select_for_table(
+    scope: &mut Scope,
+    table_name: &str,
+    columns: &[Column],
+    pk_columns: &[String],
+    user_types: &UserTypes,
+    config: &GenConfig,
+) {
+    if pk_columns.is_empty() {
+        return;
+    }
     let new_fn = scope.new_fn(&format!("select_{}", table_name));
-    new_fn.set_async(true);
     new_fn.vis("pub");
-    new_fn.arg("conn", Type::new("&mut PgConnection"));
+    add_conn_arg(new_fn, config);
+    for pk in pk_columns {
+        new_fn.arg(
+            pk,
+            Type::new(&format!("&{}", column_rs_type(columns, pk, user_types))),
+        );
+    }
+    new_fn.ret(Type::new(&format!(
+        "Result<{}, sqlx::Error>",
+        row_struct_name(table_name)
+    )));
+    let where_clause = pk_where_clause(pk_columns, 0);
+    let args_list = pk_columns.iter().join(", ");
+
+    let body = format!(
+        r##"
+    let result = sqlx::query_as!({},
+        r#"SELECT * FROM {} WHERE {}"#,
+        {}
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(result)"##,
+        row_struct_name(table_name),
+        table_name,
+        where_clause,
+        args_list
+    );
+    finish_body(new_fn, config, body);
+}
+
+fn add_update_for_table(
+    scope: &mut Scope,
+    table_name: &str,
+    columns: &[Column],
+    pk_columns: &[String],
+    user_types: &UserTypes,
+    config: &GenConfig,
+) {
+    if pk_columns.is_empty() {
+        return;
+    }
+    let new_fn = scope.new_fn(&format!("update_{}", table_name));
+    new_fn.vis("pub");
+    add_conn_arg(new_fn, config);
+    for pk in pk_columns {
+        new_fn.arg(
+            pk,
+            Type::new(&format!("&{}", column_rs_type(columns, pk, user_types))),
+        );
+    }
     new_fn.arg(
-        "id",
-        Type::new(&format!("&{}", pg_type_to_rs_type(&id_typ.1))),
+        "row",
+        Type::new(&format!("&{}", input_row_struct_name(table_name))),
     );
     new_fn.ret(Type::new(&format!(
         "Result<{}, sqlx::Error>",
         row_struct_name(table_name)
     )));
-    let insert_name_list = columns.iter().map(|c| format!("\"{}\"", c.0)).join(", ");
+
+    let set_columns: Vec<_> = columns
+        .iter()
+        .filter(|c| !pk_columns.contains(&c.0) && !c.4)
+        .collect();
+    let set_clause = set_columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("{}=${}", c.0, i + 1))
+        .join(", ");
+    let where_clause = pk_where_clause(pk_columns, set_columns.len());
+    let args_list = set_columns
+        .iter()
+        .map(|c| format!("row.{}", c.0))
+        .chain(pk_columns.iter().cloned())
+        .join(", ");
 
     let body = format!(
         r##"
     let result = sqlx::query_as!({},
-        r#"SELECT {} FROM {} WHERE id=$1"#, id
+        r#"UPDATE {} SET {}
+        WHERE {}
+        RETURNING *"#,
+        {}
     )
-    .fetch_one(&mut *conn)
+    .fetch_one(conn)
+    .await?;
+    Ok(result)"##,
+        row_struct_name(table_name),
+        table_name,
+        set_clause,
+        where_clause,
+        args_list
+    );
+    finish_body(new_fn, config, body);
+}
+
+fn add_delete_for_table(
+    scope: &mut Scope,
+    table_name: &str,
+    columns: &[Column],
+    pk_columns: &[String],
+    user_types: &UserTypes,
+    config: &GenConfig,
+) {
+    if pk_columns.is_empty() {
+        return;
+    }
+    let new_fn = scope.new_fn(&format!("delete_{}", table_name));
+    new_fn.vis("pub");
+    add_conn_arg(new_fn, config);
+    for pk in pk_columns {
+        new_fn.arg(
+            pk,
+            Type::new(&format!("&{}", column_rs_type(columns, pk, user_types))),
+        );
+    }
+    new_fn.ret(Type::new(&format!(
+        "Result<{}, sqlx::Error>",
+        row_struct_name(table_name)
+    )));
+    let where_clause = pk_where_clause(pk_columns, 0);
+    let args_list = pk_columns.iter().join(", ");
+
+    let body = format!(
+        r##"
+    let result = sqlx::query_as!({},
+        r#"DELETE FROM {} WHERE {}
+        RETURNING *"#,
+        {}
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(result)"##,
+        row_struct_name(table_name),
+        table_name,
+        where_clause,
+        args_list
+    );
+    finish_body(new_fn, config, body);
+}
+
+/// Generates `search_{table}_by_{column}`, a pgvector nearest-neighbor
+/// search over one `vector` column using `config.vector_distance_op`, so
+/// callers can match whichever index the column was built with. Returns
+/// `{Table}SearchRow`, which carries the computed distance (see
+/// `distance_field_name`) alongside the table's own columns;
+/// `add_search_struct_for_table` must have already emitted that struct.
+fn add_vector_search_for_table(
+    scope: &mut Scope,
+    table_name: &str,
+    column_name: &str,
+    columns: &[Column],
+    config: &GenConfig,
+) {
+    let new_fn = scope.new_fn(&format!("search_{}_by_{}", table_name, column_name));
+    new_fn.vis("pub");
+    add_conn_arg(new_fn, config);
+    new_fn.arg("query", Type::new("&pgvector::Vector"));
+    new_fn.arg("limit", Type::new("i64"));
+    new_fn.ret(Type::new(&format!(
+        "Result<Vec<{}>, sqlx::Error>",
+        search_row_struct_name(table_name)
+    )));
+
+    let op = &config.vector_distance_op;
+    let distance_field = distance_field_name(columns);
+    let body = format!(
+        r##"
+    let result = sqlx::query_as!({},
+        r#"SELECT *, {} {} $1 AS {} FROM {} ORDER BY {} {} $1 LIMIT $2"#,
+        query, limit
+    )
+    .fetch_all(conn)
+    .await?;
+    Ok(result)"##,
+        search_row_struct_name(table_name),
+        column_name, op, distance_field, table_name, column_name, op
+    );
+    finish_body(new_fn, config, body);
+}
+
+fn add_list_for_table(scope: &mut Scope, table_name: &str, config: &GenConfig) {
+    let new_fn = scope.new_fn(&format!("list_{}", table_name));
+    new_fn.vis("pub");
+    add_conn_arg(new_fn, config);
+    new_fn.ret(Type::new(&format!(
+        "Result<Vec<{}>, sqlx::Error>",
+        row_struct_name(table_name)
+    )));
+
+    let body = format!(
+        r##"
+    let result = sqlx::query_as!({},
+        r#"SELECT * FROM {}"#
+    )
+    .fetch_all(conn)
     .await?;
     Ok(result)"##,
         row_struct_name(table_name),
-        insert_name_list,
         table_name
     );
-    new_fn.line(body);
+    finish_body(new_fn, config, body);
+}
+
+/// Builds a `col=$n AND col2=$n+1 ...` clause for the given primary-key
+/// columns, with placeholder numbering starting after `preceding_params`
+/// other `$n` parameters already used earlier in the same query.
+fn pk_where_clause(pk_columns: &[String], preceding_params: usize) -> String {
+    pk_columns
+        .iter()
+        .enumerate()
+        .map(|(i, pk)| format!("{}=${}", pk, preceding_params + i + 1))
+        .join(" AND ")
 }
 
 fn input_row_struct_name(table_name: &str) -> String {
@@ -137,52 +819,251 @@ fn row_struct_name(table_name: &str) -> String {
     format!("{}Row", to_class_case(table_name))
 }
 
+fn search_row_struct_name(table_name: &str) -> String {
+    format!("{}SearchRow", to_class_case(table_name))
+}
+
+/// The field/column name used for a vector search's computed distance.
+/// Usually `distance`, but a table that already has a `distance` column
+/// (plausible for geo/distance schemas) would otherwise collide with it in
+/// `{Table}SearchRow`, so fall back to `search_distance` in that case.
+fn distance_field_name(columns: &[Column]) -> &'static str {
+    if columns.iter().any(|c| c.0 == "distance") {
+        "search_distance"
+    } else {
+        "distance"
+    }
+}
+
+/// Emits `{Table}SearchRow`: every column of `{Table}Row` plus the computed
+/// distance column (named by `distance_field_name`), for the result of a
+/// pgvector nearest-neighbor search. A dedicated struct is needed because
+/// `query_as!` requires the destination struct's fields to match the query's
+/// output columns, and the search query has one more column (the distance)
+/// than the table itself.
+fn add_search_struct_for_table(
+    scope: &mut Scope,
+    table_name: &str,
+    columns: &[Column],
+    user_types: &UserTypes,
+    config: &GenConfig,
+) {
+    let search_row_name = search_row_struct_name(table_name);
+    let new_struct = scope.new_struct(&search_row_name);
+    new_struct.derive("Debug");
+    new_struct.derive("Clone");
+    new_struct.vis("pub");
+    // `distance` is always an f64, so this struct never supports Eq/Hash.
+    add_extra_derives(new_struct, config, &search_row_name, true);
+    for (name, ty, udt_name, is_nullable, _) in columns {
+        let ty = pg_type_to_rs_type(ty, udt_name, user_types);
+        let ty = if is_nullable == "YES" {
+            format!("Option<{}>", ty)
+        } else {
+            ty
+        };
+        new_struct.field(&format!("pub {}", name), &ty);
+    }
+    new_struct.field(&format!("pub {}", distance_field_name(columns)), "f64");
+}
+
 fn add_structs_for_table(
     scope: &mut Scope,
     table_name: &str,
-    columns: &[(String, String, String)],
+    columns: &[Column],
+    user_types: &UserTypes,
+    pk_columns: &[String],
+    config: &GenConfig,
 ) {
     let columns = columns
         .iter()
-        .map(|(name, ty, is_option)| {
-            let ty = pg_type_to_rs_type(ty);
+        .map(|(name, ty, udt_name, is_option, has_server_value)| {
+            let ty = pg_type_to_rs_type(ty, udt_name, user_types);
             let ty = if is_option == "YES" {
                 format!("Option<{}>", ty)
             } else {
                 ty
             };
-            (name, ty)
+            (name, ty, *has_server_value)
         })
         .collect_vec();
-    let new_struct = scope.new_struct(&row_struct_name(table_name));
+    let has_float = has_float_field(columns.iter().map(|c| c.1.as_str()));
+    let row_name = row_struct_name(table_name);
+    let new_struct = scope.new_struct(&row_name);
     new_struct.derive("Debug");
     new_struct.derive("Clone");
     new_struct.vis("pub");
+    add_extra_derives(new_struct, config, &row_name, has_float);
     for column in &columns {
         new_struct.field(&format!("pub {}", column.0), &column.1);
     }
-    let new_in_struct = scope.new_struct(&input_row_struct_name(table_name));
+    let input_row_name = input_row_struct_name(table_name);
+    let new_in_struct = scope.new_struct(&input_row_name);
     new_in_struct.vis("pub");
+    add_extra_derives(new_in_struct, config, &input_row_name, has_float);
     for column in columns {
-        if column.0 != "id" && column.0 != "created_at" {
+        if !pk_columns.contains(column.0) && !column.2 {
             new_in_struct.field(&format!("pub {}", column.0), &column.1);
         }
     }
 }
 
-fn pg_type_to_rs_type(pg_type: &str) -> String {
+fn pg_type_to_rs_type(pg_type: &str, udt_name: &str, user_types: &UserTypes) -> String {
+    // pgvector ships `vector` as an extension base type, so it shows up as
+    // USER-DEFINED like an enum or composite, but isn't one of ours to
+    // generate; map it straight to the pgvector crate's own type.
+    if udt_name == "vector" {
+        return "pgvector::Vector".to_string();
+    }
     match pg_type {
-        "integer" => "i32",
-        "bigint" => "i64",
-        "real" => "f32",
-        "text" => "String",
-        "character varying" => "String",
-        "timestamp with time zone" => "chrono::DateTime<chrono::Utc>",
-        "boolean" => "bool",
-        "bytea" => "Vec<u8>", // is this right?
-        "USER-DEFINED" => "()",
-        "numeric" => "bigdecimal::BigDecimal",
-        _ => panic!("Unknown type: {}", pg_type),
-    }
-    .to_string()
+        "smallint" => "i16".to_string(),
+        "integer" => "i32".to_string(),
+        "bigint" => "i64".to_string(),
+        "real" => "f32".to_string(),
+        "double precision" => "f64".to_string(),
+        "text" => "String".to_string(),
+        "character varying" => "String".to_string(),
+        "uuid" => "uuid::Uuid".to_string(),
+        "json" | "jsonb" => "serde_json::Value".to_string(),
+        "date" => "chrono::NaiveDate".to_string(),
+        "time without time zone" => "chrono::NaiveTime".to_string(),
+        "timestamp without time zone" => "chrono::NaiveDateTime".to_string(),
+        "timestamp with time zone" => "chrono::DateTime<chrono::Utc>".to_string(),
+        "interval" => "sqlx::postgres::types::PgInterval".to_string(),
+        "boolean" => "bool".to_string(),
+        "bytea" => "Vec<u8>".to_string(), // is this right?
+        "numeric" => "bigdecimal::BigDecimal".to_string(),
+        "USER-DEFINED" => user_types
+            .get(udt_name)
+            .cloned()
+            .unwrap_or_else(|| unknown_type_placeholder(pg_type, udt_name)),
+        // information_schema reports "ARRAY" for the data_type of array
+        // columns regardless of element type; the element type only shows up
+        // in udt_name, prefixed with an underscore (e.g. `_int4`, `_text`).
+        "ARRAY" => format!(
+            "Vec<{}>",
+            pg_typname_to_rs_type(udt_name.trim_start_matches('_'), user_types)
+        ),
+        _ => unknown_type_placeholder(pg_type, udt_name),
+    }
+}
+
+/// Like [`pg_type_to_rs_type`], but keyed off the short `pg_catalog` type
+/// name (e.g. `int4`, `varchar`) rather than the `information_schema` spelling
+/// (e.g. `integer`, `character varying`). Used to resolve array element types
+/// and the field types of composite types, which the catalog only reports in
+/// their short form.
+fn pg_typname_to_rs_type(typname: &str, user_types: &UserTypes) -> String {
+    match typname {
+        "int2" => "i16".to_string(),
+        "int4" => "i32".to_string(),
+        "int8" => "i64".to_string(),
+        "float4" => "f32".to_string(),
+        "float8" => "f64".to_string(),
+        "text" | "varchar" => "String".to_string(),
+        "uuid" => "uuid::Uuid".to_string(),
+        "json" | "jsonb" => "serde_json::Value".to_string(),
+        "date" => "chrono::NaiveDate".to_string(),
+        "time" => "chrono::NaiveTime".to_string(),
+        "timestamp" => "chrono::NaiveDateTime".to_string(),
+        "timestamptz" => "chrono::DateTime<chrono::Utc>".to_string(),
+        "interval" => "sqlx::postgres::types::PgInterval".to_string(),
+        "bool" => "bool".to_string(),
+        "bytea" => "Vec<u8>".to_string(),
+        "numeric" => "bigdecimal::BigDecimal".to_string(),
+        other => user_types
+            .get(other)
+            .cloned()
+            .unwrap_or_else(|| unknown_type_placeholder(typname, typname)),
+    }
+}
+
+/// Postgres exposes far more types than we map; rather than aborting
+/// mid-schema, warn on stderr and emit a placeholder `()` field annotated
+/// with the type we couldn't resolve, so codegen still produces usable code
+/// for the rest of the schema.
+fn unknown_type_placeholder(pg_type: &str, udt_name: &str) -> String {
+    eprintln!(
+        "warning: no Rust mapping for Postgres type `{}` (udt_name `{}`), emitting placeholder",
+        pg_type, udt_name
+    );
+    format!("/* unknown pg type: {} */ ()", pg_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pg_type_to_rs_type_maps_scalars() {
+        let user_types = UserTypes::new();
+        assert_eq!(pg_type_to_rs_type("integer", "int4", &user_types), "i32");
+        assert_eq!(pg_type_to_rs_type("text", "text", &user_types), "String");
+        assert_eq!(pg_type_to_rs_type("boolean", "bool", &user_types), "bool");
+    }
+
+    #[test]
+    fn pg_type_to_rs_type_maps_vector_before_user_defined() {
+        let user_types = UserTypes::new();
+        assert_eq!(
+            pg_type_to_rs_type("USER-DEFINED", "vector", &user_types),
+            "pgvector::Vector"
+        );
+    }
+
+    #[test]
+    fn pg_type_to_rs_type_resolves_user_defined_from_map() {
+        let mut user_types = UserTypes::new();
+        user_types.insert("mood".to_string(), "Mood".to_string());
+        assert_eq!(
+            pg_type_to_rs_type("USER-DEFINED", "mood", &user_types),
+            "Mood"
+        );
+    }
+
+    #[test]
+    fn pg_type_to_rs_type_resolves_arrays_via_element_udt_name() {
+        let user_types = UserTypes::new();
+        assert_eq!(
+            pg_type_to_rs_type("ARRAY", "_int4", &user_types),
+            "Vec<i32>"
+        );
+    }
+
+    #[test]
+    fn pg_typname_to_rs_type_maps_short_catalog_names() {
+        let user_types = UserTypes::new();
+        assert_eq!(pg_typname_to_rs_type("int4", &user_types), "i32");
+        assert_eq!(pg_typname_to_rs_type("varchar", &user_types), "String");
+        assert_eq!(pg_typname_to_rs_type("timestamptz", &user_types), "chrono::DateTime<chrono::Utc>");
+    }
+
+    #[test]
+    fn pg_typname_to_rs_type_resolves_user_defined_from_map() {
+        let mut user_types = UserTypes::new();
+        user_types.insert("mood".to_string(), "Mood".to_string());
+        assert_eq!(pg_typname_to_rs_type("mood", &user_types), "Mood");
+    }
+
+    #[test]
+    fn pg_array_cast_casts_plain_columns_to_one_dimensional_arrays() {
+        assert_eq!(pg_array_cast("integer", "int4"), "int4[]");
+    }
+
+    #[test]
+    fn pg_array_cast_casts_array_columns_to_two_dimensional_arrays() {
+        assert_eq!(pg_array_cast("ARRAY", "_int4"), "int4[][]");
+    }
+
+    #[test]
+    fn pk_where_clause_builds_single_column_clause() {
+        let pk = vec!["id".to_string()];
+        assert_eq!(pk_where_clause(&pk, 0), "id=$1");
+    }
+
+    #[test]
+    fn pk_where_clause_numbers_placeholders_after_preceding_params() {
+        let pk = vec!["tenant_id".to_string(), "id".to_string()];
+        assert_eq!(pk_where_clause(&pk, 2), "tenant_id=$3 AND id=$4");
+    }
 }